@@ -0,0 +1,49 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Error and result types used throughout `glean_core`.
+
+use std::fmt;
+use std::io;
+
+/// The kind of error that occurred.
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// An I/O error, e.g. while persisting a ping to disk.
+    Io(io::Error),
+}
+
+/// The error type used throughout `glean_core`.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+}
+
+impl Error {
+    /// Get the kind of error that occurred.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Io(e) => write!(f, "IO error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error {
+            kind: ErrorKind::Io(error),
+        }
+    }
+}
+
+/// A specialized [`Result`](std::result::Result) type for `glean_core` operations.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,79 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The top-level Glean coordinator.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::Result;
+use crate::metrics::PingType;
+use crate::upload::PingUploadManager;
+
+/// The top-level Glean instance.
+///
+/// Owns the [`PingUploadManager`](crate::upload::PingUploadManager), so pings assembled
+/// by [`PingType::submit`](crate::metrics::PingType::submit) are durably queued for
+/// upload, and assembles ping bodies on request.
+#[derive(Debug)]
+pub struct Glean {
+    upload_manager: PingUploadManager,
+    next_document_id: AtomicU64,
+}
+
+impl Glean {
+    /// Create a new Glean instance, persisting pending pings under `data_path`.
+    pub fn new<P: Into<PathBuf>>(data_path: P) -> Self {
+        Self {
+            upload_manager: PingUploadManager::new(data_path),
+            next_document_id: AtomicU64::new(0),
+        }
+    }
+
+    /// The upload manager responsible for durability, ordering and retry of pings.
+    pub fn upload_manager(&self) -> &PingUploadManager {
+        &self.upload_manager
+    }
+
+    /// Assemble `ping`'s body, embedding `reason` in its metadata, ready to be handed to
+    /// the upload manager.
+    ///
+    /// This snapshot doesn't track metric state to fold into the payload, so the
+    /// assembled body is a minimal stand-in rather than a real Glean ping; `glean_core`
+    /// always has something to send here, so this never returns `None` in practice, but
+    /// callers (and [`PingType::submit`](crate::metrics::PingType::submit)) should treat
+    /// `None` as "nothing worth sending" once metric recording exists to make that
+    /// decision.
+    ///
+    /// ## Arguments
+    ///
+    /// * `ping` - the ping type being assembled.
+    /// * `reason` - why the ping is being submitted; embedded in the assembled body.
+    /// * `log_ping` - whether to log the assembled ping.
+    ///
+    /// ## Return value
+    ///
+    /// `Some((document_id, path, body))` on success.
+    pub fn assemble_ping(
+        &self,
+        ping: &PingType,
+        reason: Option<&str>,
+        log_ping: bool,
+    ) -> Result<Option<(String, String, String)>> {
+        let document_id = self.next_document_id.fetch_add(1, Ordering::Relaxed).to_string();
+        let path = format!("/submit/{}/{document_id}", ping.name);
+        let body = format!(
+            "ping_type={} include_client_id={} reason={}",
+            ping.name,
+            ping.include_client_id,
+            reason.unwrap_or("")
+        );
+
+        if log_ping {
+            eprintln!("{body}");
+        }
+
+        Ok(Some((document_id, path, body)))
+    }
+}
@@ -0,0 +1,203 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Lock-free accumulation for histograms on hot instrumentation paths.
+//!
+//! `Histogram` backs its bucket counts with a `HashMap<u64, u64>`, which needs a
+//! mutable borrow (and, in practice, a mutex around it) to accumulate concurrently.
+//! [`AtomicHistogram`] instead backs a fixed, pre-sized array of `AtomicU64`s, so
+//! `accumulate` is three relaxed `fetch_add`s (bucket, sum and count) with no
+//! allocation and no contention beyond what the atomics themselves need.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Bucketing, Histogram};
+
+/// A [`Bucketing`] usable for lock-free concurrent accumulation.
+///
+/// In addition to mapping a sample to its bucket minimum, a concurrent bucketing must
+/// provide a dense index into a fixed-size array, so buckets can be backed by plain
+/// atomics instead of a `HashMap` guarded by a lock.
+pub trait ConcurrentBucketing: Bucketing {
+    /// The number of buckets this bucketing addresses.
+    fn bucket_count(&self) -> usize;
+
+    /// Map a sample to a dense bucket index in `0..self.bucket_count()`.
+    fn sample_to_bucket_index(&self, sample: u64) -> usize;
+
+    /// Map a bucket index back to its bucket minimum.
+    fn bucket_index_to_minimum(&self, index: usize) -> u64;
+}
+
+/// A histogram that accumulates samples lock-free, backed by a fixed array of atomics.
+///
+/// Construction allocates the bucket array once; after that, `accumulate` never
+/// allocates and never blocks on other callers.
+#[derive(Debug)]
+pub struct AtomicHistogram<B: ConcurrentBucketing> {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    bucketing: B,
+}
+
+impl<B: ConcurrentBucketing> AtomicHistogram<B> {
+    /// Create a new histogram using the given bucketing algorithm.
+    pub fn new(bucketing: B) -> Self {
+        let buckets = (0..bucketing.bucket_count())
+            .map(|_| AtomicU64::new(0))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        Self {
+            buckets,
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            bucketing,
+        }
+    }
+
+    /// Record a single sample.
+    ///
+    /// Safe to call concurrently from any number of threads without external locking.
+    pub fn accumulate(&self, sample: u64) {
+        let index = self.bucketing.sample_to_bucket_index(sample);
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(sample, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Create a thread-local cache that buffers increments and flushes them into this
+    /// histogram when dropped or explicitly [`drain`](LocalHistogramCache::drain)ed.
+    ///
+    /// Use this to further cut contention on the shared atomics when a single thread
+    /// accumulates many samples in a tight loop.
+    pub fn local_cache(&self) -> LocalHistogramCache<'_, B> {
+        LocalHistogramCache {
+            histogram: self,
+            local_buckets: RefCell::new(vec![0; self.buckets.len()]),
+            local_count: RefCell::new(0),
+            local_sum: RefCell::new(0),
+        }
+    }
+
+    /// Read all atomics into a regular [`Histogram`] for serialization/ping assembly.
+    ///
+    /// This is eventually consistent: any [`LocalHistogramCache`] that hasn't flushed
+    /// yet isn't reflected, so a snapshot may momentarily undercount in-flight samples.
+    ///
+    /// The atomic path doesn't track `sum_of_squares` (it isn't needed for ping
+    /// assembly), so `variance`/`std_dev` on the returned histogram aren't meaningful
+    /// (they come out as `0.0`); `mean` is derived from `sum` and `count` and is.
+    pub fn snapshot(&self) -> Histogram<B>
+    where
+        B: Clone,
+    {
+        let mut values = std::collections::HashMap::new();
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            let count = bucket.load(Ordering::Relaxed);
+            if count > 0 {
+                let minimum = self.bucketing.bucket_index_to_minimum(index);
+                values.insert(minimum, count);
+            }
+        }
+
+        Histogram {
+            values,
+            count: self.count.load(Ordering::Relaxed),
+            sum: self.sum.load(Ordering::Relaxed),
+            sum_of_squares: 0,
+            bucketing: self.bucketing.clone(),
+        }
+    }
+}
+
+/// A thread-local buffer of bucket increments for an [`AtomicHistogram`].
+///
+/// Increments are buffered as plain, non-atomic counters and flushed into the shared
+/// atomic collector on [`drain`](LocalHistogramCache::drain) or on drop.
+pub struct LocalHistogramCache<'h, B: ConcurrentBucketing> {
+    histogram: &'h AtomicHistogram<B>,
+    local_buckets: RefCell<Vec<u64>>,
+    local_count: RefCell<u64>,
+    local_sum: RefCell<u64>,
+}
+
+impl<'h, B: ConcurrentBucketing> LocalHistogramCache<'h, B> {
+    /// Buffer a single sample locally, without touching the shared atomics.
+    pub fn accumulate(&self, sample: u64) {
+        let index = self.histogram.bucketing.sample_to_bucket_index(sample);
+        self.local_buckets.borrow_mut()[index] += 1;
+        *self.local_count.borrow_mut() += 1;
+        *self.local_sum.borrow_mut() += sample;
+    }
+
+    /// Flush all buffered increments into the shared [`AtomicHistogram`].
+    pub fn drain(&self) {
+        let mut local_buckets = self.local_buckets.borrow_mut();
+        for (index, count) in local_buckets.iter_mut().enumerate() {
+            if *count > 0 {
+                self.histogram.buckets[index].fetch_add(*count, Ordering::Relaxed);
+                *count = 0;
+            }
+        }
+
+        let mut local_count = self.local_count.borrow_mut();
+        if *local_count > 0 {
+            self.histogram.count.fetch_add(*local_count, Ordering::Relaxed);
+            *local_count = 0;
+        }
+
+        let mut local_sum = self.local_sum.borrow_mut();
+        if *local_sum > 0 {
+            self.histogram.sum.fetch_add(*local_sum, Ordering::Relaxed);
+            *local_sum = 0;
+        }
+    }
+}
+
+impl<'h, B: ConcurrentBucketing> Drop for LocalHistogramCache<'h, B> {
+    fn drop(&mut self) {
+        self.drain();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::histogram::PrecomputedBounds;
+
+    #[test]
+    fn local_cache_is_invisible_until_it_drains() {
+        let hist = AtomicHistogram::new(PrecomputedBounds::new(&[10, 20]));
+
+        {
+            let cache = hist.local_cache();
+            cache.accumulate(5);
+            cache.accumulate(15);
+            assert_eq!(hist.snapshot().count(), 0);
+        } // `cache` is dropped here, which flushes it.
+
+        let snapshot = hist.snapshot();
+        assert_eq!(snapshot.count(), 2);
+        assert_eq!(snapshot.sum(), 20);
+    }
+
+    #[test]
+    fn local_cache_explicit_drain_flushes_immediately() {
+        let hist = AtomicHistogram::new(PrecomputedBounds::new(&[10]));
+        let cache = hist.local_cache();
+
+        cache.accumulate(5);
+        cache.drain();
+        assert_eq!(hist.snapshot().count(), 1);
+
+        // Draining again (and dropping afterwards) must not double-count.
+        cache.drain();
+        drop(cache);
+        assert_eq!(hist.snapshot().count(), 1);
+        assert_eq!(hist.snapshot().sum(), 5);
+    }
+}
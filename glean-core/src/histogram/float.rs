@@ -0,0 +1,217 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A floating-point analogue of [`Histogram`](super::Histogram).
+//!
+//! `Histogram` is hard-wired to `u64` samples, which forces callers measuring
+//! sub-integer durations, ratios, or byte-fractions to pre-quantize and lose precision.
+//! `HistogramF64` accepts `f64` samples directly and keeps its own `sum` in floating
+//! point, while exposing the same `accumulate`/`sum`/`count`/`values`/`is_empty` surface.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+/// A bucketing algorithm for [`HistogramF64`].
+///
+/// Mirrors [`Bucketing`](super::Bucketing), but maps `f64` samples to `f64` bucket
+/// boundaries instead of `u64` ones. Any exponential or functional bucketing formula
+/// used for the integer `Histogram` can be provided here as an `f64` analogue.
+pub trait BucketingF64 {
+    /// Get the bucket's minimum value the sample falls into.
+    fn sample_to_bucket_minimum(&self, sample: f64) -> f64;
+}
+
+/// A bucket minimum, keyed by its bit pattern so it can live in a `HashMap`.
+///
+/// `f64` doesn't implement `Eq`/`Hash`. Bucket minimums are always the deterministic
+/// output of a [`BucketingF64`] rather than arbitrary user input, so comparing their bit
+/// patterns (rather than their numeric value) is safe here and sidesteps the usual
+/// pitfalls of using floats as map keys.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BucketKey(u64);
+
+impl BucketKey {
+    fn from_f64(value: f64) -> Self {
+        BucketKey(value.to_bits())
+    }
+
+    /// The bucket minimum this key represents.
+    pub fn minimum(&self) -> f64 {
+        f64::from_bits(self.0)
+    }
+}
+
+impl Eq for BucketKey {}
+
+impl Hash for BucketKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+/// A histogram over `f64` samples.
+///
+/// Stores the counts per bucket and tracks the count of added samples and the total
+/// sum, same as [`Histogram`](super::Histogram). NaN and negative samples can't be
+/// bucketed or summed meaningfully, so `accumulate` drops them and records an
+/// instrumentation error instead of corrupting `sum`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramF64<B> {
+    /// Mapping bucket's minimum to sample count.
+    values: HashMap<BucketKey, u64>,
+
+    /// The count of samples added.
+    count: u64,
+    /// The total sum of samples.
+    sum: f64,
+    /// The count of samples dropped for being NaN or negative.
+    error_count: u64,
+
+    /// The bucketing algorithm used.
+    bucketing: B,
+}
+
+impl<B: BucketingF64> HistogramF64<B> {
+    /// Create a new histogram using the given bucketing algorithm.
+    pub fn new(bucketing: B) -> Self {
+        Self {
+            values: HashMap::new(),
+            count: 0,
+            sum: 0.0,
+            error_count: 0,
+            bucketing,
+        }
+    }
+
+    /// Get the number of buckets in this histogram.
+    pub fn bucket_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Add a single value to this histogram.
+    ///
+    /// NaN and negative samples are dropped and counted in
+    /// [`error_count`](HistogramF64::error_count) rather than corrupting `sum`.
+    pub fn accumulate(&mut self, sample: f64) {
+        if sample.is_nan() || sample < 0.0 {
+            self.error_count += 1;
+            return;
+        }
+
+        let bucket_min = self.bucketing.sample_to_bucket_minimum(sample);
+        let entry = self.values.entry(BucketKey::from_f64(bucket_min)).or_insert(0);
+        *entry += 1;
+        self.sum += sample;
+        self.count += 1;
+    }
+
+    /// Get the total sum of values recorded in this histogram.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Get the total count of values recorded in this histogram.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the count of samples dropped for being NaN or negative.
+    pub fn error_count(&self) -> u64 {
+        self.error_count
+    }
+
+    /// Get the filled values.
+    pub fn values(&self) -> &HashMap<BucketKey, u64> {
+        &self.values
+    }
+
+    /// Check if this histogram recorded any values.
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+}
+
+/// An `f64` analogue of [`PrecomputedBounds`](super::PrecomputedBounds), bucketing by
+/// explicit, user-supplied upper bounds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrecomputedBoundsF64 {
+    /// The sorted upper bounds of each bucket.
+    bounds: Vec<f64>,
+}
+
+impl PrecomputedBoundsF64 {
+    /// Create a new bucketing from a slice of upper bounds.
+    ///
+    /// The bounds don't need to be pre-sorted, but must not be empty or contain NaN.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `bounds` is empty or contains NaN.
+    pub fn new(bounds: &[f64]) -> Self {
+        assert!(!bounds.is_empty(), "PrecomputedBoundsF64 requires at least one bound");
+        assert!(
+            bounds.iter().all(|b| !b.is_nan()),
+            "PrecomputedBoundsF64 bounds must not be NaN"
+        );
+
+        let mut bounds = bounds.to_vec();
+        bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Self { bounds }
+    }
+}
+
+impl BucketingF64 for PrecomputedBoundsF64 {
+    fn sample_to_bucket_minimum(&self, sample: f64) -> f64 {
+        self.bounds
+            .iter()
+            .find(|&&bound| sample <= bound)
+            .copied()
+            .unwrap_or_else(|| *self.bounds.last().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accumulate_drops_nan_and_negative_samples() {
+        let mut hist = HistogramF64::new(PrecomputedBoundsF64::new(&[10.0]));
+
+        hist.accumulate(f64::NAN);
+        hist.accumulate(-1.0);
+
+        assert_eq!(hist.count(), 0);
+        assert_eq!(hist.error_count(), 2);
+        assert_eq!(hist.sum(), 0.0);
+    }
+
+    #[test]
+    fn accumulate_accepts_negative_zero() {
+        // -0.0 is negative under `is_sign_negative()` but not under `< 0.0`; it's a
+        // legitimate zero sample and must not be dropped as an error.
+        let mut hist = HistogramF64::new(PrecomputedBoundsF64::new(&[10.0]));
+
+        hist.accumulate(-0.0);
+
+        assert_eq!(hist.count(), 1);
+        assert_eq!(hist.error_count(), 0);
+        assert_eq!(hist.sum(), 0.0);
+    }
+
+    #[test]
+    fn accumulate_accepts_positive_samples() {
+        let mut hist = HistogramF64::new(PrecomputedBoundsF64::new(&[10.0, 20.0]));
+
+        hist.accumulate(5.0);
+        hist.accumulate(15.0);
+
+        assert_eq!(hist.count(), 2);
+        assert_eq!(hist.error_count(), 0);
+        assert_eq!(hist.sum(), 20.0);
+        assert_eq!(hist.bucket_count(), 2);
+    }
+}
@@ -5,14 +5,21 @@
 //! A simple histogram implementation for exponential histograms.
 
 use std::collections::HashMap;
+use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+pub use concurrent::{AtomicHistogram, ConcurrentBucketing, LocalHistogramCache};
 pub use exponential::PrecomputedExponential;
+pub use float::{BucketKey, BucketingF64, HistogramF64, PrecomputedBoundsF64};
 pub use functional::Functional;
+pub use precomputed_bounds::PrecomputedBounds;
 
+mod concurrent;
 mod exponential;
+mod float;
 mod functional;
+mod precomputed_bounds;
 
 /// A histogram.
 ///
@@ -40,6 +47,9 @@ pub struct Histogram<B> {
     count: u64,
     /// The total sum of samples.
     sum: u64,
+    /// The total sum of squares of samples, used to derive variance/std-dev.
+    #[serde(default)]
+    sum_of_squares: u64,
 
     /// The bucketing algorithm used.
     bucketing: B,
@@ -66,6 +76,7 @@ impl<B: Bucketing> Histogram<B> {
         let entry = self.values.entry(bucket_min).or_insert(0);
         *entry += 1;
         self.sum = self.sum.saturating_add(sample);
+        self.sum_of_squares = self.sum_of_squares.saturating_add(sample.saturating_mul(sample));
         self.count += 1;
     }
 
@@ -88,4 +99,267 @@ impl<B: Bucketing> Histogram<B> {
     pub fn is_empty(&self) -> bool {
         self.count() == 0
     }
-}
\ No newline at end of file
+
+    /// Calculates the approximate value at the given percentile.
+    ///
+    /// `p` is clamped to `[0, 100]`. Because the histogram only keeps per-bucket counts,
+    /// not individual samples, this walks the occupied buckets in order and returns the
+    /// minimum of the bucket in which the target rank falls, linearly interpolated
+    /// towards the next occupied bucket's minimum for a tighter estimate. The result is
+    /// therefore only accurate to within one bucket width — the same bounded-error
+    /// trade-off made by low-latency histogram collectors that bucket logarithmically
+    /// instead of storing every sample.
+    ///
+    /// Returns `0` for an empty histogram.
+    pub fn percentile(&self, p: f64) -> u64 {
+        self.percentiles(&[p])[0]
+    }
+
+    /// Calculates multiple percentiles at once.
+    ///
+    /// Equivalent to calling [`percentile`](Histogram::percentile) for each entry in
+    /// `ps`, but only sorts the occupied buckets once.
+    pub fn percentiles(&self, ps: &[f64]) -> Vec<u64> {
+        if self.count == 0 {
+            return vec![0; ps.len()];
+        }
+
+        let mut buckets: Vec<(u64, u64)> =
+            self.values.iter().map(|(&min, &count)| (min, count)).collect();
+        buckets.sort_by_key(|&(min, _)| min);
+
+        ps.iter()
+            .map(|&p| {
+                let p = p.clamp(0.0, 100.0);
+                // The rank (1-based) of the sample we're looking for.
+                let target = ((p / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+
+                let mut cumulative = 0;
+                for (i, &(bucket_min, count)) in buckets.iter().enumerate() {
+                    cumulative += count;
+                    if cumulative >= target {
+                        return match buckets.get(i + 1) {
+                            // Interpolate towards the next occupied bucket's minimum,
+                            // based on how far into this bucket's count the target rank
+                            // falls. Bucketings like `PrecomputedBounds` use `u64::MAX`
+                            // as a sentinel minimum for an unbounded "+Inf" overflow
+                            // bucket; interpolating towards it is meaningless (and
+                            // overflows the addition below), so just return this
+                            // bucket's minimum instead, same as when there's no next
+                            // bucket at all.
+                            Some(&(next_min, _)) if next_min != u64::MAX => {
+                                let into_bucket = count - (cumulative - target);
+                                let frac = into_bucket as f64 / count as f64;
+                                bucket_min + ((next_min - bucket_min) as f64 * frac) as u64
+                            }
+                            _ => bucket_min,
+                        };
+                    }
+                }
+
+                // Unreachable in practice (the loop above always covers `self.count`
+                // samples), but fall back to the last bucket's minimum just in case.
+                buckets.last().map(|&(min, _)| min).unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// Get the arithmetic mean of values recorded in this histogram.
+    ///
+    /// Returns `0.0` for an empty histogram.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    /// Get the variance of values recorded in this histogram, i.e. `E[x²] - E[x]²`.
+    ///
+    /// Clamped to `0.0` to guard against tiny negative results from floating-point
+    /// cancellation. Returns `0.0` for an empty histogram.
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let mean_of_squares = self.sum_of_squares as f64 / self.count as f64;
+        (mean_of_squares - mean * mean).max(0.0)
+    }
+
+    /// Get the standard deviation of values recorded in this histogram.
+    ///
+    /// Returns `0.0` for an empty histogram.
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// The width, in characters, of the ASCII bar for the most populous bucket.
+const DISPLAY_BAR_WIDTH: u64 = 40;
+
+/// Render a bucket minimum for display, special-casing the `u64::MAX` sentinel that
+/// bucketings like `PrecomputedBounds` use for the implicit, unbounded "+Inf" bucket so
+/// it doesn't show up as a meaningless huge number.
+fn format_bucket_min(min: u64) -> String {
+    if min == u64::MAX {
+        "+Inf".to_string()
+    } else {
+        min.to_string()
+    }
+}
+
+impl<B: Bucketing> fmt::Display for Histogram<B> {
+    /// Renders a terminal-friendly summary of this histogram: sample count, smallest and
+    /// largest occupied bucket minimums, mean, standard deviation, and an ASCII bar per
+    /// occupied bucket scaled to the largest bucket count. Handy for eyeballing a
+    /// distribution during local debugging, e.g. in `log_ping` output.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "count = {}", self.count)?;
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets: Vec<(u64, u64)> =
+            self.values.iter().map(|(&min, &count)| (min, count)).collect();
+        buckets.sort_by_key(|&(min, _)| min);
+
+        // These are bucket minimums, not true sample extremes: a sample is reported
+        // under the minimum of the bucket it falls into, not its own value.
+        writeln!(
+            f,
+            "min bucket = {}",
+            buckets.first().map(|&(min, _)| format_bucket_min(min)).unwrap_or_default()
+        )?;
+        writeln!(
+            f,
+            "max bucket = {}",
+            buckets.last().map(|&(min, _)| format_bucket_min(min)).unwrap_or_default()
+        )?;
+        writeln!(f, "mean = {:.2}", self.mean())?;
+        writeln!(f, "std dev = {:.2}", self.std_dev())?;
+
+        // At least one bucket is occupied (we returned above for an empty histogram),
+        // so `max_count` is always > 0.
+        let max_count = buckets.iter().map(|&(_, count)| count).max().unwrap_or(1);
+        for (bucket_min, count) in buckets {
+            let bar_len = count * DISPLAY_BAR_WIDTH / max_count;
+            writeln!(
+                f,
+                "{:>12} | {:<6} {}",
+                format_bucket_min(bucket_min),
+                count,
+                "#".repeat(bar_len as usize)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::histogram::PrecomputedBounds;
+
+    fn histogram_with_samples(bounds: &[u64], samples: &[u64]) -> Histogram<PrecomputedBounds> {
+        let mut hist = Histogram {
+            values: HashMap::new(),
+            count: 0,
+            sum: 0,
+            sum_of_squares: 0,
+            bucketing: PrecomputedBounds::new(bounds),
+        };
+        for &sample in samples {
+            hist.accumulate(sample);
+        }
+        hist
+    }
+
+    #[test]
+    fn percentile_with_a_single_occupied_bucket_returns_its_minimum() {
+        let hist = histogram_with_samples(&[100], &[5, 5, 5]);
+        assert_eq!(hist.percentile(0.0), 100);
+        assert_eq!(hist.percentile(50.0), 100);
+        assert_eq!(hist.percentile(100.0), 100);
+    }
+
+    #[test]
+    fn percentile_interpolates_towards_the_next_occupied_bucket() {
+        // Bucket minimums 10, 20, 30 with counts 4, 4, 2 (count = 10).
+        let hist = histogram_with_samples(
+            &[10, 20, 30],
+            &[10, 10, 10, 10, 20, 20, 20, 20, 30, 30],
+        );
+
+        // Rank 3 falls in the first bucket, 3/4 of the way through it, so it
+        // interpolates 75% of the way from 10 towards the next bucket's minimum of 20.
+        assert_eq!(hist.percentile(30.0), 17);
+
+        // Rank 10 is the last sample, which falls in the last occupied bucket; there's
+        // no next bucket to interpolate towards, so it returns that bucket's minimum.
+        assert_eq!(hist.percentile(100.0), 30);
+    }
+
+    #[test]
+    fn percentile_does_not_interpolate_towards_the_overflow_bucket() {
+        // Bound is 10; the sample at 100 overflows into the implicit `+Inf` bucket,
+        // whose minimum is the `u64::MAX` sentinel. Interpolating towards that would
+        // overflow the `bucket_min + delta` addition.
+        let hist = histogram_with_samples(&[10], &[5, 5, 100]);
+        assert_eq!(hist.percentile(50.0), 10);
+    }
+
+    #[test]
+    fn percentile_on_an_empty_histogram_is_zero() {
+        let hist = histogram_with_samples(&[100], &[]);
+        assert_eq!(hist.percentile(50.0), 0);
+    }
+
+    #[test]
+    fn percentiles_matches_percentile_called_individually() {
+        let hist = histogram_with_samples(&[10, 20, 30], &[10, 15, 20, 25, 30]);
+        assert_eq!(
+            hist.percentiles(&[10.0, 50.0, 90.0]),
+            vec![
+                hist.percentile(10.0),
+                hist.percentile(50.0),
+                hist.percentile(90.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn variance_of_constant_samples_is_exactly_zero() {
+        // All samples equal, so variance is exactly 0 rather than merely close to it,
+        // proving the `.max(0.0)` clamp isn't masking a real non-zero result here.
+        let hist = histogram_with_samples(&[10], &[10, 10, 10, 10]);
+        assert_eq!(hist.variance(), 0.0);
+    }
+
+    #[test]
+    fn variance_and_std_dev_match_the_textbook_formula() {
+        let hist = histogram_with_samples(&[10], &[2, 4, 4, 4, 6]);
+        assert_eq!(hist.mean(), 4.0);
+        // mean_of_squares = (4 + 16 + 16 + 16 + 36) / 5 = 17.6; variance = 17.6 - 16 = 1.6
+        assert!((hist.variance() - 1.6).abs() < 1e-9);
+        assert!((hist.std_dev() - 1.6_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mean_variance_and_std_dev_are_zero_for_an_empty_histogram() {
+        let hist = histogram_with_samples(&[10], &[]);
+        assert_eq!(hist.mean(), 0.0);
+        assert_eq!(hist.variance(), 0.0);
+        assert_eq!(hist.std_dev(), 0.0);
+    }
+
+    #[test]
+    fn display_renders_plus_inf_for_the_overflow_bucket_instead_of_u64_max() {
+        let hist = histogram_with_samples(&[10], &[5, 100]);
+        let rendered = hist.to_string();
+
+        assert!(rendered.contains("max bucket = +Inf"), "{rendered}");
+        assert!(!rendered.contains("18446744073709551615"), "{rendered}");
+    }
+}
@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Bucketing with explicit, user-supplied upper bounds.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Bucketing, ConcurrentBucketing, Histogram};
+
+/// A bucketing that uses explicit, user-supplied upper bounds rather than an
+/// exponential or functional formula.
+///
+/// This is the layout Prometheus-style backends expect: a fixed set of `le` ("less or
+/// equal") bounds, each reporting the count of every sample at or below it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrecomputedBounds {
+    /// The sorted upper bounds of each bucket.
+    bounds: Vec<u64>,
+}
+
+impl PrecomputedBounds {
+    /// Create a new bucketing from a slice of upper bounds.
+    ///
+    /// The bounds don't need to be pre-sorted, but must not be empty.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `bounds` is empty.
+    pub fn new(bounds: &[u64]) -> Self {
+        assert!(!bounds.is_empty(), "PrecomputedBounds requires at least one bound");
+
+        let mut bounds = bounds.to_vec();
+        bounds.sort_unstable();
+        Self { bounds }
+    }
+
+    /// The sorted upper bounds backing this bucketing.
+    pub fn bounds(&self) -> &[u64] {
+        &self.bounds
+    }
+}
+
+/// The sentinel bucket minimum for samples above the largest explicit bound, i.e. the
+/// implicit `+Inf` bucket. Chosen so it never matches a real `le` bound and therefore
+/// never gets folded into a finite bucket by [`Histogram::cumulative_buckets`].
+const OVERFLOW_BUCKET: u64 = u64::MAX;
+
+impl Bucketing for PrecomputedBounds {
+    fn sample_to_bucket_minimum(&self, sample: u64) -> u64 {
+        self.bounds
+            .iter()
+            .find(|&&bound| sample <= bound)
+            .copied()
+            .unwrap_or(OVERFLOW_BUCKET)
+    }
+}
+
+impl ConcurrentBucketing for PrecomputedBounds {
+    fn bucket_count(&self) -> usize {
+        // One dense slot per explicit bound, plus one for the implicit `+Inf` overflow
+        // bucket (samples above the largest bound).
+        self.bounds.len() + 1
+    }
+
+    fn sample_to_bucket_index(&self, sample: u64) -> usize {
+        self.bounds
+            .iter()
+            .position(|&bound| sample <= bound)
+            .unwrap_or(self.bounds.len())
+    }
+
+    fn bucket_index_to_minimum(&self, index: usize) -> u64 {
+        self.bounds.get(index).copied().unwrap_or(OVERFLOW_BUCKET)
+    }
+}
+
+impl Histogram<PrecomputedBounds> {
+    /// Get the cumulative ("less-or-equal") bucket counts for Prometheus-style export.
+    ///
+    /// Returns, for each of this histogram's bounds `le`, the count of all samples
+    /// `<= le`. The returned counts are monotonically non-decreasing; samples above the
+    /// largest bound fall into the implicit `+Inf` bucket, which is `self.count()` and
+    /// isn't included here since callers already have it.
+    pub fn cumulative_buckets(&self) -> Vec<(u64, u64)> {
+        let mut buckets: Vec<(u64, u64)> =
+            self.values().iter().map(|(&min, &count)| (min, count)).collect();
+        buckets.sort_by_key(|&(min, _)| min);
+
+        let mut result = Vec::with_capacity(self.bucketing.bounds.len());
+        let mut cumulative = 0u64;
+        let mut iter = buckets.into_iter().peekable();
+        for &le in &self.bucketing.bounds {
+            while let Some(&(min, count)) = iter.peek() {
+                if min > le {
+                    break;
+                }
+                cumulative += count;
+                iter.next();
+            }
+            result.push((le, cumulative));
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn histogram_with_samples(bounds: &[u64], samples: &[u64]) -> Histogram<PrecomputedBounds> {
+        let mut hist = Histogram {
+            values: HashMap::new(),
+            count: 0,
+            sum: 0,
+            sum_of_squares: 0,
+            bucketing: PrecomputedBounds::new(bounds),
+        };
+        for &sample in samples {
+            hist.accumulate(sample);
+        }
+        hist
+    }
+
+    #[test]
+    fn cumulative_buckets_are_monotonically_non_decreasing() {
+        let hist = histogram_with_samples(&[10, 20, 30], &[5, 15, 15, 25, 40]);
+        let cumulative = hist.cumulative_buckets();
+
+        assert_eq!(cumulative, vec![(10, 1), (20, 3), (30, 4)]);
+        assert!(cumulative.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn cumulative_buckets_excludes_the_implicit_plus_inf_bucket() {
+        // The sample at 40 overflows the largest bound (30) into the implicit `+Inf`
+        // bucket, which is reflected in `count()` but has no `le` entry of its own.
+        let hist = histogram_with_samples(&[10, 20, 30], &[40]);
+
+        assert_eq!(hist.cumulative_buckets(), vec![(10, 0), (20, 0), (30, 0)]);
+        assert_eq!(hist.count(), 1);
+    }
+
+    #[test]
+    fn cumulative_buckets_has_one_entry_per_bound() {
+        let hist = histogram_with_samples(&[1, 2, 3, 4], &[]);
+        assert_eq!(hist.cumulative_buckets().len(), 4);
+    }
+}
@@ -0,0 +1,15 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `glean_core` is the core implementation shared by all Glean language bindings.
+
+pub mod error;
+pub mod histogram;
+pub mod metrics;
+pub mod upload;
+
+mod glean;
+
+pub use crate::glean::Glean;
+pub use crate::metrics::PingType;
@@ -33,7 +33,39 @@ impl PingType {
         }
     }
 
-    /// Send the ping.
+    /// Submit the ping for eventual upload, recording why it was submitted.
+    ///
+    /// `glean` assembles the ping and embeds `reason` in its metadata; the assembled
+    /// ping is then handed to the [`PingUploadManager`](crate::upload::PingUploadManager),
+    /// which persists it to the on-disk FIFO so it survives a restart and schedules its
+    /// upload. See the `upload` module for how assembly, persistence and retry are split
+    /// between `glean_core` and the embedding application.
+    ///
+    /// ## Arguments
+    ///
+    /// * `glean` - the Glean instance to use to assemble the ping.
+    /// * `reason` - why the ping is being submitted, e.g. `startup`, `background`, or
+    /// `max_capacity`. Recorded in the ping's metadata. `None` if there's no specific
+    /// reason.
+    /// * `log_ping` - whether to log the ping after assembly.
+    ///
+    /// ## Return value
+    ///
+    /// Returns `true` if the ping was assembled and queued, `false` if `glean` decided
+    /// there was nothing worth sending (e.g. an empty ping).
+    pub fn submit(&self, glean: &Glean, reason: Option<&str>, log_ping: bool) -> Result<bool> {
+        let Some((document_id, path, body)) = glean.assemble_ping(self, reason, log_ping)? else {
+            return Ok(false);
+        };
+
+        glean.upload_manager().enqueue_ping(&document_id, &path, &body)?;
+        Ok(true)
+    }
+
+    /// Send the ping, without recording a reason.
+    ///
+    /// Kept for callers that don't have a specific reason to report; prefer
+    /// [`submit`](PingType::submit) when one is available.
     ///
     /// ## Arguments
     ///
@@ -42,8 +74,8 @@ impl PingType {
     ///
     /// ## Return value
     ///
-    /// See [`Glean#send_ping`](../struct.Glean.html#method.send_ping) for details.
+    /// See [`submit`](PingType::submit) for details.
     pub fn send(&self, glean: &Glean, log_ping: bool) -> Result<bool> {
-        glean.send_ping(self, log_ping)
+        self.submit(glean, None, log_ping)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,273 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Ping upload scheduling and durability.
+//!
+//! `PingType::submit` assembles a ping and persists it to disk; it's up to the
+//! embedding application to actually perform the HTTP upload. `glean_core` owns
+//! durability and submission order via [`PingUploadManager`], and hands out work
+//! through a pull-based [`PingUploadTask`] API so embedders can drive their own
+//! networking and retry loop while reporting results back via
+//! [`PingUploadManager::process_ping_upload_response`].
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+
+pub use directory::PingDirectoryManager;
+
+mod directory;
+
+/// The initial backoff delay after a recoverable upload failure.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// The maximum backoff delay, no matter how many consecutive failures occurred.
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// The outcome of attempting to upload a ping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadResult {
+    /// The ping was uploaded successfully; delete it from the queue.
+    Success,
+    /// The upload failed in a way that might succeed if retried, e.g. a network
+    /// timeout or a 5xx response. The ping stays queued and is retried with backoff.
+    Recoverable,
+    /// The upload failed in a way that will never succeed, e.g. a 4xx response. The
+    /// ping is deleted from the queue without being retried.
+    Unrecoverable,
+}
+
+/// A ping that has been assembled and persisted to disk, awaiting upload.
+#[derive(Debug, Clone)]
+struct QueuedPing {
+    document_id: String,
+    path: String,
+    body: String,
+    file_path: PathBuf,
+}
+
+/// A unit of work handed to the embedding application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PingUploadTask {
+    /// A ping is ready to be uploaded.
+    Upload {
+        /// Identifies the ping; pass this back to
+        /// [`PingUploadManager::process_ping_upload_response`].
+        document_id: String,
+        /// The path the ping should be uploaded to.
+        path: String,
+        /// The assembled body of the ping.
+        body: String,
+    },
+    /// Nothing is ready right now, but a queued ping is waiting out a retry backoff.
+    Wait {
+        /// How long the embedder should wait before asking for another task.
+        next_request_in: Duration,
+    },
+    /// There's nothing left to upload.
+    Done,
+}
+
+/// Per-ping retry bookkeeping, kept only for pings that have failed at least once.
+#[derive(Debug)]
+struct RetryState {
+    attempts: u32,
+    next_attempt_at: Instant,
+}
+
+/// Coordinates on-disk persistence and pull-based retrieval of pending pings.
+///
+/// `glean_core` owns assembly, durability (so submission order survives a restart) and
+/// retry scheduling; the embedding application owns the actual network transport. This
+/// separates ping assembly from transport, letting embedders supply their own
+/// networking stack.
+#[derive(Debug)]
+pub struct PingUploadManager {
+    directory_manager: PingDirectoryManager,
+    queue: Mutex<VecDeque<QueuedPing>>,
+    in_flight: Mutex<HashMap<String, QueuedPing>>,
+    retry_state: Mutex<HashMap<String, RetryState>>,
+}
+
+impl PingUploadManager {
+    /// Create a new upload manager, restoring any pings left pending by a previous run.
+    pub fn new<P: Into<PathBuf>>(data_path: P) -> Self {
+        let directory_manager = PingDirectoryManager::new(data_path);
+        let pending = directory_manager.scan_pending_pings();
+
+        Self {
+            directory_manager,
+            queue: Mutex::new(pending.into()),
+            in_flight: Mutex::new(HashMap::new()),
+            retry_state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Persist an assembled ping to disk and enqueue it for upload.
+    pub fn enqueue_ping(&self, document_id: &str, path: &str, body: &str) -> Result<()> {
+        let queued = self.directory_manager.enqueue_ping(document_id, path, body)?;
+        self.queue.lock().unwrap().push_back(queued);
+        Ok(())
+    }
+
+    /// Get the next unit of work for the embedding application.
+    ///
+    /// Pings are handed out in the order they were enqueued; a ping put back after a
+    /// recoverable failure goes to the back of the queue. A ping stays "in flight"
+    /// (it won't be handed out again) until
+    /// [`process_ping_upload_response`](Self::process_ping_upload_response) is called
+    /// for it.
+    pub fn get_upload_task(&self) -> PingUploadTask {
+        let mut queue = self.queue.lock().unwrap();
+        let retry_state = self.retry_state.lock().unwrap();
+
+        let now = Instant::now();
+        let front_is_backing_off = queue.front().is_some_and(|front| {
+            retry_state
+                .get(&front.document_id)
+                .is_some_and(|state| state.next_attempt_at > now)
+        });
+
+        if front_is_backing_off {
+            let next_request_in = retry_state
+                .values()
+                .map(|state| state.next_attempt_at.saturating_duration_since(now))
+                .min()
+                .unwrap_or(INITIAL_BACKOFF);
+            return PingUploadTask::Wait { next_request_in };
+        }
+        drop(retry_state);
+
+        match queue.pop_front() {
+            Some(ping) => {
+                let task = PingUploadTask::Upload {
+                    document_id: ping.document_id.clone(),
+                    path: ping.path.clone(),
+                    body: ping.body.clone(),
+                };
+                self.in_flight
+                    .lock()
+                    .unwrap()
+                    .insert(ping.document_id.clone(), ping);
+                task
+            }
+            None => PingUploadTask::Done,
+        }
+    }
+
+    /// Report the result of uploading the ping with the given `document_id`.
+    ///
+    /// On [`UploadResult::Success`] or [`UploadResult::Unrecoverable`] the ping is
+    /// deleted from disk. On [`UploadResult::Recoverable`] it's put back on the queue
+    /// and its next attempt is delayed by a capped exponential backoff.
+    pub fn process_ping_upload_response(&self, document_id: &str, result: UploadResult) {
+        let Some(ping) = self.in_flight.lock().unwrap().remove(document_id) else {
+            return;
+        };
+
+        match result {
+            UploadResult::Success | UploadResult::Unrecoverable => {
+                self.directory_manager.delete_ping(&ping);
+                self.retry_state.lock().unwrap().remove(document_id);
+            }
+            UploadResult::Recoverable => {
+                {
+                    let mut retry_state = self.retry_state.lock().unwrap();
+                    let state = retry_state.entry(document_id.to_string()).or_insert(RetryState {
+                        attempts: 0,
+                        next_attempt_at: Instant::now(),
+                    });
+                    state.attempts += 1;
+                    // `attempts` is 1 on the first recoverable failure, so shift by
+                    // `attempts - 1` to make the first delay equal to `INITIAL_BACKOFF`.
+                    let backoff = INITIAL_BACKOFF
+                        .saturating_mul(1u32 << (state.attempts - 1).min(16))
+                        .min(MAX_BACKOFF);
+                    state.next_attempt_at = Instant::now() + backoff;
+                }
+
+                self.queue.lock().unwrap().push_back(ping);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    fn temp_data_path(test_name: &str) -> PathBuf {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!(
+            "glean_upload_manager_test_{test_name}_{}_{id}",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn recoverable_failures_back_off_exponentially() {
+        let data_path = temp_data_path("backoff");
+        let manager = PingUploadManager::new(&data_path);
+        manager.enqueue_ping("doc1", "/path", "{}").unwrap();
+
+        assert!(matches!(manager.get_upload_task(), PingUploadTask::Upload { .. }));
+        manager.process_ping_upload_response("doc1", UploadResult::Recoverable);
+        let first_backoff = manager
+            .retry_state
+            .lock()
+            .unwrap()
+            .get("doc1")
+            .unwrap()
+            .next_attempt_at
+            .saturating_duration_since(Instant::now());
+
+        // The first recoverable failure should wait `INITIAL_BACKOFF`, not double it.
+        assert!(first_backoff <= INITIAL_BACKOFF);
+        assert!(first_backoff > INITIAL_BACKOFF - Duration::from_millis(100));
+
+        // Re-deliver without actually waiting out the backoff, simulating a second
+        // delivery attempt so the test doesn't need to sleep for real.
+        let ping = manager.queue.lock().unwrap().pop_front().unwrap();
+        manager.in_flight.lock().unwrap().insert("doc1".to_string(), ping);
+        manager.process_ping_upload_response("doc1", UploadResult::Recoverable);
+        let second_backoff = manager
+            .retry_state
+            .lock()
+            .unwrap()
+            .get("doc1")
+            .unwrap()
+            .next_attempt_at
+            .saturating_duration_since(Instant::now());
+
+        assert!(second_backoff <= INITIAL_BACKOFF * 2);
+        assert!(second_backoff > INITIAL_BACKOFF * 2 - Duration::from_millis(100));
+
+        let _ = std::fs::remove_dir_all(&data_path);
+    }
+
+    #[test]
+    fn success_deletes_the_ping_and_clears_retry_state() {
+        let data_path = temp_data_path("success");
+        let manager = PingUploadManager::new(&data_path);
+        manager.enqueue_ping("doc1", "/path", "{}").unwrap();
+
+        assert!(matches!(manager.get_upload_task(), PingUploadTask::Upload { .. }));
+        manager.process_ping_upload_response("doc1", UploadResult::Recoverable);
+        assert!(manager.retry_state.lock().unwrap().contains_key("doc1"));
+
+        let ping = manager.queue.lock().unwrap().pop_front().unwrap();
+        manager.in_flight.lock().unwrap().insert("doc1".to_string(), ping);
+        manager.process_ping_upload_response("doc1", UploadResult::Success);
+
+        assert!(!manager.retry_state.lock().unwrap().contains_key("doc1"));
+        assert_eq!(manager.get_upload_task(), PingUploadTask::Done);
+
+        let _ = std::fs::remove_dir_all(&data_path);
+    }
+}